@@ -0,0 +1,126 @@
+use chrono::Utc;
+use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use jsonwebtoken::errors::ErrorKind;
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+
+/// How long a minted access token stays valid for.
+const TOKEN_TTL_SECS: i64 = 15 * 60;
+
+/// Once a token has this many seconds left, `chat_completion` refreshes its
+/// cached customer config from Firestore on the side instead of serving it
+/// stale for the token's remaining lifetime.
+pub const REFRESH_WINDOW_SECS: i64 = 60;
+
+/// Claims carried by a felafax access token.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Claims {
+    pub customer_id: String,
+    /// Snapshot of the customer's provider at mint time. Self-describing
+    /// only — `chat_completion` always dispatches against the live
+    /// `CustomerConfig` in `config_cache`/Firestore, so this field doesn't
+    /// drive routing and can go stale if the customer's config changes
+    /// before the token expires.
+    pub selected_llm_name: String,
+    pub exp: i64,
+}
+
+/// Mints a short-lived, HS256-signed access token for a customer.
+pub fn mint_token(secret: &str, customer_id: &str, selected_llm_name: &str) -> Result<(String, i64), Error> {
+    let claims = Claims {
+        customer_id: customer_id.to_string(),
+        selected_llm_name: selected_llm_name.to_string(),
+        exp: Utc::now().timestamp() + TOKEN_TTL_SECS,
+    };
+
+    let token = encode(
+        &Header::new(jsonwebtoken::Algorithm::HS256),
+        &claims,
+        &EncodingKey::from_secret(secret.as_bytes()),
+    )
+    .map_err(|e| Error::InvalidToken(e.to_string()))?;
+
+    Ok((token, TOKEN_TTL_SECS))
+}
+
+/// Verifies an access token locally, without touching Firestore.
+pub fn verify_token(secret: &str, token: &str) -> Result<Claims, Error> {
+    decode::<Claims>(
+        token,
+        &DecodingKey::from_secret(secret.as_bytes()),
+        &Validation::new(jsonwebtoken::Algorithm::HS256),
+    )
+    .map(|data| data.claims)
+    .map_err(|e| match e.kind() {
+        ErrorKind::ExpiredSignature => Error::TokenExpired,
+        _ => Error::InvalidToken(e.to_string()),
+    })
+}
+
+/// Whether a token is close enough to expiry that its cached config should
+/// be refreshed from Firestore.
+pub fn is_near_expiry(claims: &Claims) -> bool {
+    claims.exp - Utc::now().timestamp() < REFRESH_WINDOW_SECS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mint_then_verify_round_trips_the_customer_id() {
+        let (token, expires_in) = mint_token("secret", "customer-123", "openai").unwrap();
+        assert_eq!(expires_in, TOKEN_TTL_SECS);
+
+        let claims = verify_token("secret", &token).unwrap();
+        assert_eq!(claims.customer_id, "customer-123");
+        assert_eq!(claims.selected_llm_name, "openai");
+    }
+
+    #[test]
+    fn verify_rejects_a_token_signed_with_a_different_secret() {
+        let (token, _) = mint_token("secret", "customer-123", "openai").unwrap();
+        let err = verify_token("other-secret", &token).unwrap_err();
+        assert!(matches!(err, Error::InvalidToken(_)));
+    }
+
+    #[test]
+    fn verify_reports_an_expired_token_distinctly() {
+        let claims = Claims {
+            customer_id: "customer-123".to_string(),
+            selected_llm_name: "openai".to_string(),
+            // Past jsonwebtoken's default 60s leeway so this reliably expires.
+            exp: Utc::now().timestamp() - 120,
+        };
+        let token = encode(
+            &Header::new(jsonwebtoken::Algorithm::HS256),
+            &claims,
+            &EncodingKey::from_secret(b"secret"),
+        )
+        .unwrap();
+
+        let err = verify_token("secret", &token).unwrap_err();
+        assert!(matches!(err, Error::TokenExpired));
+    }
+
+    #[test]
+    fn is_near_expiry_true_within_the_refresh_window() {
+        let claims = Claims {
+            customer_id: "customer-123".to_string(),
+            selected_llm_name: "openai".to_string(),
+            exp: Utc::now().timestamp() + REFRESH_WINDOW_SECS - 1,
+        };
+        assert!(is_near_expiry(&claims));
+    }
+
+    #[test]
+    fn is_near_expiry_false_well_before_expiry() {
+        let claims = Claims {
+            customer_id: "customer-123".to_string(),
+            selected_llm_name: "openai".to_string(),
+            exp: Utc::now().timestamp() + TOKEN_TTL_SECS,
+        };
+        assert!(!is_near_expiry(&claims));
+    }
+}
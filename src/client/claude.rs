@@ -0,0 +1,164 @@
+use async_stream::stream;
+use futures_util::StreamExt;
+use serde_json::Value;
+
+use crate::client::sse::SseDecoder;
+use crate::client::traits::{ChatStream, LlmClient};
+use crate::error::Error;
+use crate::types::{
+    OaiChatChunkChoice, OaiChatCompletionChunk, OaiChatCompletionRequest, OaiChatCompletionResponse,
+    OaiChatDelta,
+};
+
+const CLAUDE_API_URL: &str = "https://api.anthropic.com/v1/messages";
+
+#[derive(Debug, Default, Clone)]
+pub struct Claude {
+    api_key: String,
+}
+
+impl LlmClient for Claude {
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn with_api_key(mut self, api_key: &str) -> Self {
+        self.api_key = api_key.to_string();
+        self
+    }
+
+    async fn chat(&self, request: OaiChatCompletionRequest) -> Result<OaiChatCompletionResponse, Error> {
+        let client = reqwest::Client::new();
+        let response = client
+            .post(CLAUDE_API_URL)
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", "2023-06-01")
+            .json(&request)
+            .send()
+            .await
+            .map_err(|e| {
+                if e.is_timeout() || e.is_connect() {
+                    Error::ProviderUnavailable(e.to_string())
+                } else {
+                    Error::Provider(e.to_string())
+                }
+            })?;
+
+        let status = response.status();
+        if status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return Err(Error::ProviderUnavailable(format!(
+                "claude returned {status}"
+            )));
+        }
+        if !status.is_success() {
+            return Err(Error::Provider(format!("claude returned {status}")));
+        }
+
+        response
+            .json::<OaiChatCompletionResponse>()
+            .await
+            .map_err(|e| Error::Provider(e.to_string()))
+    }
+
+    async fn chat_stream(&self, request: OaiChatCompletionRequest) -> Result<ChatStream, Error> {
+        let client = reqwest::Client::new();
+        let response = client
+            .post(CLAUDE_API_URL)
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", "2023-06-01")
+            .json(&request)
+            .send()
+            .await
+            .map_err(|e| {
+                if e.is_timeout() || e.is_connect() {
+                    Error::ProviderUnavailable(e.to_string())
+                } else {
+                    Error::Provider(e.to_string())
+                }
+            })?;
+
+        let status = response.status();
+        if status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return Err(Error::ProviderUnavailable(format!(
+                "claude returned {status}"
+            )));
+        }
+        if !status.is_success() {
+            return Err(Error::Provider(format!("claude returned {status}")));
+        }
+
+        let mut bytes_stream = response.bytes_stream();
+
+        // Claude's wire format doesn't match OpenAI's chunk shape, so each
+        // event needs translating rather than just parsing through.
+        let stream = stream! {
+            let mut decoder = SseDecoder::new();
+            let mut message_id = String::new();
+            let mut model = String::new();
+
+            while let Some(chunk) = bytes_stream.next().await {
+                let chunk = match chunk {
+                    Ok(chunk) => chunk,
+                    Err(e) => {
+                        yield Err(Error::Provider(e.to_string()));
+                        return;
+                    }
+                };
+
+                for payload in decoder.feed(&chunk) {
+                    let event: Value = match serde_json::from_str(&payload) {
+                        Ok(event) => event,
+                        Err(e) => {
+                            yield Err(Error::Provider(e.to_string()));
+                            continue;
+                        }
+                    };
+
+                    match event.get("type").and_then(Value::as_str) {
+                        Some("message_start") => {
+                            if let Some(message) = event.get("message") {
+                                message_id = message
+                                    .get("id")
+                                    .and_then(Value::as_str)
+                                    .unwrap_or_default()
+                                    .to_string();
+                                model = message
+                                    .get("model")
+                                    .and_then(Value::as_str)
+                                    .unwrap_or_default()
+                                    .to_string();
+                            }
+                        }
+                        Some("content_block_delta") => {
+                            let text = event
+                                .get("delta")
+                                .and_then(|d| d.get("text"))
+                                .and_then(Value::as_str)
+                                .unwrap_or_default()
+                                .to_string();
+
+                            yield Ok(OaiChatCompletionChunk {
+                                id: message_id.clone(),
+                                object: "chat.completion.chunk".to_string(),
+                                model: model.clone(),
+                                choices: vec![OaiChatChunkChoice {
+                                    index: 0,
+                                    delta: OaiChatDelta {
+                                        role: None,
+                                        content: Some(text),
+                                    },
+                                    finish_reason: None,
+                                }],
+                                usage: None,
+                            });
+                        }
+                        Some("message_stop") => return,
+                        _ => {}
+                    }
+                }
+            }
+        };
+
+        Ok(Box::pin(stream))
+    }
+}
@@ -0,0 +1,113 @@
+use async_stream::stream;
+use futures_util::StreamExt;
+
+use crate::client::sse::SseDecoder;
+use crate::client::traits::{ChatStream, LlmClient};
+use crate::error::Error;
+use crate::types::{OaiChatCompletionChunk, OaiChatCompletionRequest, OaiChatCompletionResponse};
+
+const JAMBA_API_URL: &str = "https://api.ai21.com/studio/v1/chat/completions";
+
+/// Client for AI21's Jamba models.
+#[derive(Debug, Default, Clone)]
+pub struct Mamba {
+    api_key: String,
+}
+
+impl LlmClient for Mamba {
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn with_api_key(mut self, api_key: &str) -> Self {
+        self.api_key = api_key.to_string();
+        self
+    }
+
+    async fn chat(&self, request: OaiChatCompletionRequest) -> Result<OaiChatCompletionResponse, Error> {
+        let client = reqwest::Client::new();
+        let response = client
+            .post(JAMBA_API_URL)
+            .bearer_auth(&self.api_key)
+            .json(&request)
+            .send()
+            .await
+            .map_err(|e| {
+                if e.is_timeout() || e.is_connect() {
+                    Error::ProviderUnavailable(e.to_string())
+                } else {
+                    Error::Provider(e.to_string())
+                }
+            })?;
+
+        let status = response.status();
+        if status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return Err(Error::ProviderUnavailable(format!(
+                "jamba returned {status}"
+            )));
+        }
+        if !status.is_success() {
+            return Err(Error::Provider(format!("jamba returned {status}")));
+        }
+
+        response
+            .json::<OaiChatCompletionResponse>()
+            .await
+            .map_err(|e| Error::Provider(e.to_string()))
+    }
+
+    async fn chat_stream(&self, request: OaiChatCompletionRequest) -> Result<ChatStream, Error> {
+        let client = reqwest::Client::new();
+        let response = client
+            .post(JAMBA_API_URL)
+            .bearer_auth(&self.api_key)
+            .json(&request)
+            .send()
+            .await
+            .map_err(|e| {
+                if e.is_timeout() || e.is_connect() {
+                    Error::ProviderUnavailable(e.to_string())
+                } else {
+                    Error::Provider(e.to_string())
+                }
+            })?;
+
+        let status = response.status();
+        if status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return Err(Error::ProviderUnavailable(format!(
+                "jamba returned {status}"
+            )));
+        }
+        if !status.is_success() {
+            return Err(Error::Provider(format!("jamba returned {status}")));
+        }
+
+        let mut bytes_stream = response.bytes_stream();
+
+        // Jamba's SSE payloads already follow the OpenAI chunk shape.
+        let stream = stream! {
+            let mut decoder = SseDecoder::new();
+            while let Some(chunk) = bytes_stream.next().await {
+                let chunk = match chunk {
+                    Ok(chunk) => chunk,
+                    Err(e) => {
+                        yield Err(Error::Provider(e.to_string()));
+                        return;
+                    }
+                };
+
+                for payload in decoder.feed(&chunk) {
+                    if payload == "[DONE]" {
+                        return;
+                    }
+                    match serde_json::from_str::<OaiChatCompletionChunk>(&payload) {
+                        Ok(chunk) => yield Ok(chunk),
+                        Err(e) => yield Err(Error::Provider(e.to_string())),
+                    }
+                }
+            }
+        };
+
+        Ok(Box::pin(stream))
+    }
+}
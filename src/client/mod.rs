@@ -0,0 +1,5 @@
+pub mod claude;
+pub mod mamba;
+pub mod openai;
+pub mod sse;
+pub mod traits;
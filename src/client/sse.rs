@@ -0,0 +1,119 @@
+/// Splits a raw SSE byte stream into complete `data:` payloads, buffering
+/// partial events across reads so each provider client can focus on
+/// decoding its own event shape.
+#[derive(Default)]
+pub struct SseDecoder {
+    buffer: Vec<u8>,
+}
+
+impl SseDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feeds newly received bytes and returns any complete `data:` payloads
+    /// found so far, in order.
+    ///
+    /// Bytes are buffered raw and only decoded once a full event has been
+    /// assembled, so a multi-byte UTF-8 codepoint split across two reads
+    /// isn't corrupted into replacement characters.
+    pub fn feed(&mut self, bytes: &[u8]) -> Vec<String> {
+        self.buffer.extend_from_slice(bytes);
+        normalize_crlf(&mut self.buffer);
+
+        let mut payloads = Vec::new();
+        while let Some(pos) = find_double_newline(&self.buffer) {
+            let event: Vec<u8> = self.buffer.drain(..pos + 2).collect();
+            let event = String::from_utf8_lossy(&event);
+            for line in event.lines() {
+                if let Some(data) = line.strip_prefix("data:") {
+                    payloads.push(data.trim_start().to_string());
+                }
+            }
+        }
+
+        payloads
+    }
+}
+
+/// Collapses `"\r\n"` into `"\n"` in place so providers that frame SSE with
+/// CRLF (rather than bare LF) still hit the `"\n\n"` boundary check below. A
+/// trailing lone `\r` at the end of the buffer (its `\n` hasn't arrived yet)
+/// is left alone and gets folded in on the next `feed` call.
+fn normalize_crlf(buffer: &mut Vec<u8>) {
+    if !buffer.contains(&b'\r') {
+        return;
+    }
+
+    let mut normalized = Vec::with_capacity(buffer.len());
+    let mut i = 0;
+    while i < buffer.len() {
+        if buffer[i] == b'\r' && buffer.get(i + 1) == Some(&b'\n') {
+            normalized.push(b'\n');
+            i += 2;
+        } else {
+            normalized.push(buffer[i]);
+            i += 1;
+        }
+    }
+    *buffer = normalized;
+}
+
+/// Finds the start of the first `"\n\n"` event separator. Safe to scan over
+/// raw UTF-8 bytes because `\n` (0x0A) never occurs as part of a multi-byte
+/// codepoint's encoding.
+fn find_double_newline(buffer: &[u8]) -> Option<usize> {
+    buffer.windows(2).position(|window| window == b"\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn feeds_a_single_complete_event() {
+        let mut decoder = SseDecoder::new();
+        let payloads = decoder.feed(b"data: hello\n\n");
+        assert_eq!(payloads, vec!["hello".to_string()]);
+    }
+
+    #[test]
+    fn buffers_a_partial_event_across_reads() {
+        let mut decoder = SseDecoder::new();
+        assert!(decoder.feed(b"data: hel").is_empty());
+        assert_eq!(decoder.feed(b"lo\n\n"), vec!["hello".to_string()]);
+    }
+
+    #[test]
+    fn reassembles_a_multi_byte_utf8_codepoint_split_across_reads() {
+        let payload = "data: caf\u{e9}\n\n";
+        let bytes = payload.as_bytes();
+        // Split inside the 2-byte UTF-8 encoding of 'é'.
+        let split_at = bytes.len() - 2;
+
+        let mut decoder = SseDecoder::new();
+        assert!(decoder.feed(&bytes[..split_at]).is_empty());
+        assert_eq!(decoder.feed(&bytes[split_at..]), vec!["café".to_string()]);
+    }
+
+    #[test]
+    fn emits_multiple_events_fed_in_one_call() {
+        let mut decoder = SseDecoder::new();
+        let payloads = decoder.feed(b"data: one\n\ndata: two\n\n");
+        assert_eq!(payloads, vec!["one".to_string(), "two".to_string()]);
+    }
+
+    #[test]
+    fn handles_crlf_framed_events() {
+        let mut decoder = SseDecoder::new();
+        let payloads = decoder.feed(b"data: hello\r\n\r\n");
+        assert_eq!(payloads, vec!["hello".to_string()]);
+    }
+
+    #[test]
+    fn handles_a_crlf_boundary_split_across_reads() {
+        let mut decoder = SseDecoder::new();
+        assert!(decoder.feed(b"data: hello\r\n\r").is_empty());
+        assert_eq!(decoder.feed(b"\n"), vec!["hello".to_string()]);
+    }
+}
@@ -0,0 +1,20 @@
+use std::pin::Pin;
+
+use futures_util::Stream;
+
+use crate::error::Error;
+use crate::types::{OaiChatCompletionChunk, OaiChatCompletionRequest, OaiChatCompletionResponse};
+
+/// A boxed stream of re-encoded OpenAI-shaped completion chunks, regardless
+/// of which provider produced them.
+pub type ChatStream = Pin<Box<dyn Stream<Item = Result<OaiChatCompletionChunk, Error>> + Send>>;
+
+/// Common interface implemented by every upstream LLM provider client.
+pub trait LlmClient {
+    fn new() -> Self;
+    fn with_api_key(self, api_key: &str) -> Self;
+    async fn chat(&self, request: OaiChatCompletionRequest) -> Result<OaiChatCompletionResponse, Error>;
+
+    /// Streams the completion as a series of delta chunks, for `stream: true` requests.
+    async fn chat_stream(&self, request: OaiChatCompletionRequest) -> Result<ChatStream, Error>;
+}
@@ -0,0 +1,122 @@
+use aes_gcm::aead::{Aead, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use rand::RngCore;
+
+use crate::error::Error;
+use crate::types::CustomerConfig;
+
+const NONCE_LEN: usize = 12;
+
+/// Encrypts and decrypts provider API keys at rest with AES-256-GCM. Each
+/// stored blob is `nonce || ciphertext || tag`, base64-encoded, with a
+/// fresh random 96-bit nonce per record.
+pub struct KeyCipher {
+    cipher: Aes256Gcm,
+}
+
+impl KeyCipher {
+    /// `master_key` is the base64-encoded 32-byte AES key loaded from secrets.
+    pub fn new(master_key: &str) -> Result<Self, Error> {
+        let key_bytes = STANDARD
+            .decode(master_key)
+            .map_err(|e| Error::Decryption(e.to_string()))?;
+        if key_bytes.len() != 32 {
+            return Err(Error::Decryption(format!(
+                "master key must decode to 32 bytes, got {}",
+                key_bytes.len()
+            )));
+        }
+        let key = Key::<Aes256Gcm>::from_slice(&key_bytes);
+        Ok(Self {
+            cipher: Aes256Gcm::new(key),
+        })
+    }
+
+    pub fn encrypt(&self, plaintext: &str) -> Result<String, Error> {
+        let mut nonce_bytes = [0u8; NONCE_LEN];
+        OsRng.fill_bytes(&mut nonce_bytes);
+        let nonce = Nonce::from_slice(&nonce_bytes);
+
+        let ciphertext = self
+            .cipher
+            .encrypt(nonce, plaintext.as_bytes())
+            .map_err(|e| Error::Encryption(e.to_string()))?;
+
+        let mut blob = Vec::with_capacity(NONCE_LEN + ciphertext.len());
+        blob.extend_from_slice(&nonce_bytes);
+        blob.extend_from_slice(&ciphertext);
+
+        Ok(STANDARD.encode(blob))
+    }
+
+    pub fn decrypt(&self, blob_b64: &str) -> Result<String, Error> {
+        let blob = STANDARD
+            .decode(blob_b64)
+            .map_err(|e| Error::Decryption(e.to_string()))?;
+
+        if blob.len() < NONCE_LEN {
+            return Err(Error::Decryption("ciphertext shorter than nonce".to_string()));
+        }
+
+        let (nonce_bytes, ciphertext) = blob.split_at(NONCE_LEN);
+        let nonce = Nonce::from_slice(nonce_bytes);
+
+        let plaintext = self
+            .cipher
+            .decrypt(nonce, ciphertext)
+            .map_err(|e| Error::Decryption(e.to_string()))?;
+
+        String::from_utf8(plaintext).map_err(|e| Error::Decryption(e.to_string()))
+    }
+
+    /// Decrypts a customer's stored API key for the given provider.
+    pub fn decrypt_provider_key(
+        &self,
+        customer_config: &CustomerConfig,
+        provider: &str,
+    ) -> Result<String, Error> {
+        let llm_config = customer_config
+            .llm_configs
+            .get(provider)
+            .ok_or_else(|| Error::InvalidRequest(format!("no config for provider '{provider}'")))?;
+        self.decrypt(&llm_config.api_key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_cipher() -> KeyCipher {
+        let key = STANDARD.encode([7u8; 32]);
+        KeyCipher::new(&key).unwrap()
+    }
+
+    #[test]
+    fn encrypt_then_decrypt_round_trips() {
+        let cipher = test_cipher();
+        let blob = cipher.encrypt("sk-provider-secret").unwrap();
+        assert_eq!(cipher.decrypt(&blob).unwrap(), "sk-provider-secret");
+    }
+
+    #[test]
+    fn decrypt_rejects_a_tampered_blob() {
+        let cipher = test_cipher();
+        let blob = cipher.encrypt("sk-provider-secret").unwrap();
+
+        let mut raw = STANDARD.decode(&blob).unwrap();
+        let last = raw.len() - 1;
+        raw[last] ^= 0xff;
+        let tampered = STANDARD.encode(raw);
+
+        assert!(matches!(cipher.decrypt(&tampered), Err(Error::Decryption(_))));
+    }
+
+    #[test]
+    fn new_rejects_a_master_key_that_is_not_32_bytes() {
+        let short_key = STANDARD.encode([1u8; 16]);
+        assert!(matches!(KeyCipher::new(&short_key), Err(Error::Decryption(_))));
+    }
+}
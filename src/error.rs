@@ -0,0 +1,40 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("firestore error: {0}")]
+    Firestore(String),
+
+    #[error("clickhouse error: {0}")]
+    Clickhouse(String),
+
+    #[error("provider error: {0}")]
+    Provider(String),
+
+    /// A provider failure worth retrying against the next provider in the
+    /// routing chain (5xx, timeout, upstream rate-limit).
+    #[error("provider temporarily unavailable: {0}")]
+    ProviderUnavailable(String),
+
+    #[error("invalid request: {0}")]
+    InvalidRequest(String),
+
+    #[error("access token expired")]
+    TokenExpired,
+
+    #[error("invalid access token: {0}")]
+    InvalidToken(String),
+
+    #[error("failed to decrypt stored provider key: {0}")]
+    Decryption(String),
+
+    #[error("failed to encrypt provider key: {0}")]
+    Encryption(String),
+}
+
+impl Error {
+    /// Whether a routing chain should try the next provider after this error.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, Error::ProviderUnavailable(_))
+    }
+}
@@ -0,0 +1,38 @@
+use crate::error::Error;
+use crate::types::CustomerConfig;
+
+/// Thin wrapper around the Firestore project we read customer configuration from.
+pub struct Firestore {
+    project_id: String,
+}
+
+impl Firestore {
+    pub fn new(project_id: &str) -> Self {
+        Self {
+            project_id: project_id.to_string(),
+        }
+    }
+
+    pub async fn init(&self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Looks up the customer config for a given felafax token.
+    pub async fn get_customer_configs(
+        &self,
+        felafax_token: &str,
+    ) -> Result<Option<CustomerConfig>, Error> {
+        let _ = (&self.project_id, felafax_token);
+        Ok(None)
+    }
+
+    /// Looks up the customer config by customer id, used to refresh an
+    /// access token's cached config without the original credential.
+    pub async fn get_customer_config_by_id(
+        &self,
+        customer_id: &str,
+    ) -> Result<Option<CustomerConfig>, Error> {
+        let _ = (&self.project_id, customer_id);
+        Ok(None)
+    }
+}
@@ -0,0 +1,109 @@
+use std::sync::Arc;
+
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::{interval, Duration};
+
+use crate::clickhouse::Clickhouse;
+use crate::request_logs::RequestLog;
+
+const CHANNEL_CAPACITY: usize = 1024;
+const BATCH_SIZE: usize = 100;
+const FLUSH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Buffers `RequestLog` rows and flushes them to ClickHouse in batches
+/// instead of spawning an insert task per request. Cloning a `LogWriter`
+/// is cheap: it's just a handle to the channels the background task reads.
+#[derive(Clone)]
+pub struct LogWriter {
+    sender: mpsc::Sender<RequestLog>,
+    flush_requests: mpsc::Sender<oneshot::Sender<()>>,
+}
+
+impl LogWriter {
+    /// Spawns the background task that drains the channel in batches and
+    /// returns a handle for submitting rows, plus the task's `JoinHandle` so
+    /// callers that own it can await a clean exit.
+    pub fn spawn(clickhouse: Arc<Clickhouse>) -> (Self, tokio::task::JoinHandle<()>) {
+        let (sender, receiver) = mpsc::channel(CHANNEL_CAPACITY);
+        let (flush_tx, flush_rx) = mpsc::channel(1);
+        let handle = tokio::spawn(Self::run(clickhouse, receiver, flush_rx));
+        (
+            Self {
+                sender,
+                flush_requests: flush_tx,
+            },
+            handle,
+        )
+    }
+
+    /// Queues a row for the next batch flush. Backpressures the caller
+    /// (rather than dropping the row) if the channel is full.
+    pub async fn submit(&self, log: RequestLog) {
+        if self.sender.send(log).await.is_err() {
+            eprintln!("log writer task is gone; dropping request log");
+        }
+    }
+
+    /// Forces an immediate flush of whatever is currently buffered and waits
+    /// for it to finish. Used on SIGTERM/SIGINT so rows buffered since the
+    /// last periodic tick aren't lost on a graceful shutdown.
+    pub async fn flush_now(&self) {
+        let (ack_tx, ack_rx) = oneshot::channel();
+        if self.flush_requests.send(ack_tx).await.is_err() {
+            return;
+        }
+        let _ = ack_rx.await;
+    }
+
+    async fn run(
+        clickhouse: Arc<Clickhouse>,
+        mut receiver: mpsc::Receiver<RequestLog>,
+        mut flush_requests: mpsc::Receiver<oneshot::Sender<()>>,
+    ) {
+        let mut batch = Vec::with_capacity(BATCH_SIZE);
+        let mut ticker = interval(FLUSH_INTERVAL);
+
+        loop {
+            tokio::select! {
+                received = receiver.recv() => {
+                    match received {
+                        Some(log) => {
+                            batch.push(log);
+                            if batch.len() >= BATCH_SIZE {
+                                Self::flush(&clickhouse, &mut batch).await;
+                            }
+                        }
+                        // All senders dropped (shutdown): flush what's left and exit.
+                        None => {
+                            Self::flush(&clickhouse, &mut batch).await;
+                            return;
+                        }
+                    }
+                }
+                _ = ticker.tick() => {
+                    Self::flush(&clickhouse, &mut batch).await;
+                }
+                Some(ack) = flush_requests.recv() => {
+                    // Pull in anything already queued so a row submitted
+                    // just before shutdown isn't left behind for the next
+                    // select! iteration that never comes.
+                    while let Ok(log) = receiver.try_recv() {
+                        batch.push(log);
+                    }
+                    Self::flush(&clickhouse, &mut batch).await;
+                    let _ = ack.send(());
+                }
+            }
+        }
+    }
+
+    async fn flush(clickhouse: &Clickhouse, batch: &mut Vec<RequestLog>) {
+        if batch.is_empty() {
+            return;
+        }
+        if let Err(e) = clickhouse.insert_batch(batch).await {
+            eprintln!("Failed to flush request log batch: {:?}", e);
+        }
+        batch.clear();
+    }
+}
@@ -2,42 +2,131 @@
 #![allow(dead_code)]
 #![allow(unused_imports)]
 #![allow(async_fn_in_trait)]
+#![allow(clippy::too_many_arguments)]
 // #![allow(deprecated)]
 
+pub mod auth;
 pub mod clickhouse;
 pub mod client;
+pub mod encryption;
 pub mod error;
 pub mod firestore;
+pub mod log_writer;
+pub mod rate_limit;
 pub mod request_logs;
+pub mod routing;
 pub mod types;
 
+use async_stream::stream;
 use axum::{
-    extract::State, http::header::HeaderMap, http::header::AUTHORIZATION, http::StatusCode,
-    response::IntoResponse, routing::get, routing::post, Json, Router,
+    extract::State,
+    http::header::{HeaderMap, AUTHORIZATION, RETRY_AFTER},
+    http::StatusCode,
+    response::sse::{Event, Sse},
+    response::{IntoResponse, Response},
+    routing::get,
+    routing::post,
+    Json, Router,
 };
 use chrono::Utc;
 use client::traits::*;
+use futures_util::StreamExt;
 use serde_json::{json, Value};
 use shuttle_runtime::SecretStore;
+use std::collections::HashMap;
+use std::convert::Infallible;
 use std::sync::Arc;
-use types::{OaiChatCompletionRequest, OaiChatCompletionResponse};
+use std::time::Instant;
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::RwLock;
+use types::{
+    CustomerConfig, OaiChatChoice, OaiChatCompletionRequest, OaiChatCompletionResponse,
+    OaiChatMessage, TokenResponse,
+};
 use uuid::Uuid;
 
 pub struct BackendConfigs {
     secrets: SecretStore,
     firebase: Arc<firestore::Firestore>,
     clickhouse: Arc<clickhouse::Clickhouse>,
+    /// Secret access tokens are signed and verified with.
+    token_secret: String,
+    /// Customer configs keyed by customer id, populated on mint and kept
+    /// warm by the near-expiry refresh in `chat_completion` so the hot path
+    /// doesn't need a Firestore round-trip on every request.
+    config_cache: RwLock<HashMap<String, CustomerConfig>>,
+    rate_limiter: rate_limit::RateLimiter,
+    key_cipher: encryption::KeyCipher,
+    /// Buffers request logs and flushes them to ClickHouse in batches.
+    log_writer: log_writer::LogWriter,
 }
 
 async fn hello() -> &'static str {
     "Hello from Felafax 🦊\nSupported routes: /v1/chat/completions"
 }
 
+/// `POST /v1/tokens`: exchanges a customer's felafax credential for a
+/// short-lived signed access token, caching the customer config so
+/// `chat_completion` can verify the token without a Firestore hit.
+async fn mint_token(
+    headers: HeaderMap,
+    State(backend_configs): State<Arc<BackendConfigs>>,
+) -> impl IntoResponse {
+    let credential = match extract_bearer_token(&headers) {
+        Some(token) => token,
+        None => {
+            return (
+                StatusCode::UNAUTHORIZED,
+                Json(json!({ "error": "Unauthorized: Missing or invalid token." })),
+            )
+        }
+    };
+
+    let customer_config = match backend_configs.firebase.get_customer_configs(&credential).await {
+        Ok(Some(config)) => config,
+        _ => {
+            return (
+                StatusCode::UNAUTHORIZED,
+                Json(json!({ "error": "Invalid felafax token" })),
+            )
+        }
+    };
+
+    let (access_token, expires_in) = match auth::mint_token(
+        &backend_configs.token_secret,
+        &customer_config.customer_id,
+        &customer_config.selected_llm_name,
+    ) {
+        Ok(minted) => minted,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": e.to_string() })),
+            )
+        }
+    };
+
+    backend_configs
+        .config_cache
+        .write()
+        .await
+        .insert(customer_config.customer_id.clone(), customer_config);
+
+    (
+        StatusCode::OK,
+        Json(json!(TokenResponse {
+            access_token,
+            token_type: "bearer",
+            expires_in,
+        })),
+    )
+}
+
 fn extract_bearer_token(headers: &HeaderMap) -> Option<String> {
     if let Some(auth_header) = headers.get(AUTHORIZATION) {
         if let Ok(auth_str) = auth_header.to_str() {
-            if auth_str.starts_with("Bearer ") {
-                return Some(auth_str[7..].to_string());
+            if let Some(token) = auth_str.strip_prefix("Bearer ") {
+                return Some(token.to_string());
             }
         }
     }
@@ -45,23 +134,27 @@ fn extract_bearer_token(headers: &HeaderMap) -> Option<String> {
 }
 
 async fn log_stats(
-    clickhouse_client: Arc<clickhouse::Clickhouse>,
-    firebase_client: Arc<firestore::Firestore>,
+    log_writer: log_writer::LogWriter,
     status_code: StatusCode,
     felafax_token: &str,
+    request_group_id: Option<&str>,
     request: Option<&OaiChatCompletionRequest>,
     response: Option<&OaiChatCompletionResponse>,
     llm_name: Option<&str>,
-    latency: u32,
+    total_latency: u32,
+    upstream_latency: Option<u32>,
     error: Option<String>,
 ) {
-    let clickhouse_client = clickhouse_client.clone();
     let mut request_logs = request_logs::RequestLogBuilder::default();
     request_logs.id(Uuid::new_v4().to_string());
     request_logs.timestamp(Utc::now().timestamp());
 
     request_logs.customer_id(felafax_token);
 
+    if let Some(request_group_id) = request_group_id {
+        request_logs.request_group_id(request_group_id.to_string());
+    }
+
     if let Some(request) = request {
         request_logs.request(serde_json::to_string(&request).unwrap());
     }
@@ -80,7 +173,10 @@ async fn log_stats(
             request_logs.total_tokens(usage.total_tokens);
         }
     }
-    request_logs.total_latency(latency);
+    request_logs.total_latency(total_latency);
+    if let Some(upstream_latency) = upstream_latency {
+        request_logs.upstream_latency(upstream_latency);
+    }
 
     if let Some(error) = error {
         request_logs.error(error);
@@ -88,20 +184,14 @@ async fn log_stats(
 
     let request_logs = request_logs.build().unwrap();
 
-    // log in background
-    tokio::task::spawn(async move {
-        request_logs
-            .log(&clickhouse_client, &firebase_client)
-            .await
-            .unwrap_or_else(|e| eprintln!("Failed to log request: {:?}", e));
-    });
+    log_writer.submit(request_logs).await;
 }
 
 async fn log_and_respond(
-    clickhouse_client: Arc<clickhouse::Clickhouse>,
-    firebase: Arc<firestore::Firestore>,
+    log_writer: log_writer::LogWriter,
     status_code: StatusCode,
     felafax_token: &str,
+    request_group_id: Option<&str>,
     request: Option<&OaiChatCompletionRequest>,
     response: Option<&OaiChatCompletionResponse>,
     llm_name: Option<&str>,
@@ -109,14 +199,15 @@ async fn log_and_respond(
     error: Option<String>,
 ) -> impl IntoResponse {
     log_stats(
-        clickhouse_client.clone(),
-        firebase.clone(),
+        log_writer,
         status_code,
         felafax_token,
+        request_group_id,
         request,
         response,
         llm_name,
         latency,
+        None,
         error.clone(),
     )
     .await;
@@ -132,44 +223,103 @@ async fn chat_completion(
     headers: HeaderMap,
     State(backend_configs): State<Arc<BackendConfigs>>,
     Json(payload): Json<Value>,
-) -> impl IntoResponse {
+) -> Response {
+    let started = Instant::now();
+
     let felafax_token = match extract_bearer_token(&headers) {
         Some(token) => token,
         None => {
             return log_and_respond(
-                backend_configs.clickhouse.clone(),
-                backend_configs.firebase.clone(),
+                backend_configs.log_writer.clone(),
                 StatusCode::UNAUTHORIZED,
                 "",
                 None,
                 None,
                 None,
-                0,
+                None,
+                started.elapsed().as_millis() as u32,
                 Some("Unauthorized: Missing or invalid token.".to_string()),
             )
             .await
+            .into_response()
         }
     };
 
-    let customer_config = match backend_configs
-        .firebase
-        .get_customer_configs(&felafax_token)
-        .await
-    {
-        Ok(Some(config)) => config,
-        _ => {
+    let claims = match auth::verify_token(&backend_configs.token_secret, &felafax_token) {
+        Ok(claims) => claims,
+        Err(e) => {
             return log_and_respond(
-                backend_configs.clickhouse.clone(),
-                backend_configs.firebase.clone(),
+                backend_configs.log_writer.clone(),
                 StatusCode::UNAUTHORIZED,
                 &felafax_token,
                 None,
                 None,
                 None,
-                0,
-                Some("Invalid felafax token".to_string()),
+                None,
+                started.elapsed().as_millis() as u32,
+                Some(e.to_string()),
             )
             .await
+            .into_response()
+        }
+    };
+
+    if auth::is_near_expiry(&claims) {
+        if let Ok(Some(fresh_config)) = backend_configs
+            .firebase
+            .get_customer_config_by_id(&claims.customer_id)
+            .await
+        {
+            backend_configs
+                .config_cache
+                .write()
+                .await
+                .insert(claims.customer_id.clone(), fresh_config);
+        }
+    }
+
+    let cached_config = backend_configs
+        .config_cache
+        .read()
+        .await
+        .get(&claims.customer_id)
+        .cloned();
+
+    let customer_config = match cached_config {
+        Some(config) => config,
+        None => {
+            // Cache miss: this instance never minted the token (horizontal
+            // scaling) or lost it to a restart. The token itself is still
+            // valid, so fetch from Firestore instead of rejecting it.
+            let fetched = backend_configs
+                .firebase
+                .get_customer_config_by_id(&claims.customer_id)
+                .await;
+            match fetched {
+                Ok(Some(config)) => {
+                    backend_configs
+                        .config_cache
+                        .write()
+                        .await
+                        .insert(claims.customer_id.clone(), config.clone());
+                    config
+                }
+                _ => {
+                    return log_and_respond(
+                        backend_configs.log_writer.clone(),
+                        StatusCode::UNAUTHORIZED,
+                        &felafax_token,
+                        None,
+                        None,
+                        None,
+                        None,
+                        started.elapsed().as_millis() as u32,
+                        Some("No customer config found for this access token".to_string()),
+                    )
+                    .await
+                    .into_response()
+                }
+            }
         }
     };
 
@@ -177,103 +327,275 @@ async fn chat_completion(
         Ok(req) => req,
         Err(e) => {
             return log_and_respond(
-                backend_configs.clickhouse.clone(),
-                backend_configs.firebase.clone(),
+                backend_configs.log_writer.clone(),
                 StatusCode::BAD_REQUEST,
                 &felafax_token,
                 None,
                 None,
                 None,
-                0,
+                None,
+                started.elapsed().as_millis() as u32,
                 Some(format!(
                     "Error while parsing request. Maybe it's not following OpenAI spec\nError: {}",
-                    e.to_string()
+                    e
                 )),
             )
             .await
+            .into_response()
         }
     };
 
-    let llm_response = match customer_config.selected_llm_name.as_str() {
-        "claude" => {
-            let api_key = customer_config
-                .llm_configs
-                .get("claude")
-                .unwrap()
-                .api_key
-                .clone();
-            let llm_client = client::claude::Claude::new().with_api_key(api_key.as_str());
-
-            llm_client.chat(request.clone()).await
-        }
-        "openai" => {
-            let api_key = customer_config
-                .llm_configs
-                .get("openai")
-                .unwrap()
-                .api_key
-                .clone();
-            let llm_client = client::openai::OpenAI::new().with_api_key(api_key.as_str());
-
-            llm_client.chat(request.clone()).await
-        }
-        "jamba" => {
-            let api_key = customer_config
-                .llm_configs
-                .get("jamba")
-                .unwrap()
-                .api_key
-                .clone();
-            let llm_client = client::mamba::Mamba::new().with_api_key(api_key.as_str());
-
-            llm_client.chat(request.clone()).await
-        }
-        _ => {
+    if let rate_limit::Decision::Exceeded { retry_after_secs } = backend_configs
+        .rate_limiter
+        .check(&backend_configs.clickhouse, &customer_config)
+        .await
+    {
+        let mut response = log_and_respond(
+            backend_configs.log_writer.clone(),
+            StatusCode::TOO_MANY_REQUESTS,
+            &felafax_token,
+            None,
+            Some(&request),
+            None,
+            Some(&customer_config.selected_llm_name),
+            started.elapsed().as_millis() as u32,
+            Some("Rate limit exceeded for this customer".to_string()),
+        )
+        .await
+        .into_response();
+        response
+            .headers_mut()
+            .insert(RETRY_AFTER, retry_after_secs.into());
+        return response;
+    }
+
+    if request.stream.unwrap_or(false) {
+        return chat_completion_stream(backend_configs, started, felafax_token, customer_config, request).await;
+    }
+
+    let request_group_id = Uuid::new_v4().to_string();
+    let attempts = routing::dispatch(&backend_configs.key_cipher, &customer_config, &request).await;
+
+    for attempt in &attempts {
+        let status_code = match &attempt.outcome {
+            Ok(_) => StatusCode::OK,
+            Err(error::Error::InvalidRequest(_)) => StatusCode::BAD_REQUEST,
+            Err(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        log_stats(
+            backend_configs.log_writer.clone(),
+            status_code,
+            &felafax_token,
+            Some(&request_group_id),
+            Some(&request),
+            attempt.outcome.as_ref().ok(),
+            Some(&attempt.provider),
+            started.elapsed().as_millis() as u32,
+            Some(attempt.latency_ms),
+            attempt.outcome.as_ref().err().map(|e| e.to_string()),
+        )
+        .await;
+    }
+
+    match attempts.into_iter().last() {
+        Some(attempt) => match attempt.outcome {
+            Ok(response) => (StatusCode::OK, Json(serde_json::to_value(response).unwrap())).into_response(),
+            Err(e) => {
+                let status_code = if matches!(e, error::Error::InvalidRequest(_)) {
+                    StatusCode::BAD_REQUEST
+                } else {
+                    StatusCode::INTERNAL_SERVER_ERROR
+                };
+                (status_code, Json(json!({ "error": e.to_string() }))).into_response()
+            }
+        },
+        None => (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "No providers configured for this customer" })),
+        )
+            .into_response(),
+    }
+}
+
+/// Maps a `routing::dispatch_stream` attempt's outcome to the status code and
+/// error message `log_stats` expects, same classification the non-streaming
+/// path applies to `routing::Attempt`.
+fn stream_attempt_status(outcome: &Result<client::traits::ChatStream, error::Error>) -> (StatusCode, Option<String>) {
+    let status_code = match outcome {
+        Ok(_) => StatusCode::OK,
+        Err(error::Error::InvalidRequest(_)) => StatusCode::BAD_REQUEST,
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    (status_code, outcome.as_ref().err().map(|e| e.to_string()))
+}
+
+/// Handles `stream: true` requests: forwards provider SSE chunks re-encoded
+/// as OpenAI `chat.completion.chunk` frames, then logs the reconstructed
+/// response once the stream closes.
+async fn chat_completion_stream(
+    backend_configs: Arc<BackendConfigs>,
+    started: Instant,
+    felafax_token: String,
+    customer_config: CustomerConfig,
+    request: OaiChatCompletionRequest,
+) -> Response {
+    let request_group_id = Uuid::new_v4().to_string();
+    let mut attempts = routing::dispatch_stream(&backend_configs.key_cipher, &customer_config, &request).await;
+
+    let last_attempt = match attempts.pop() {
+        Some(attempt) => attempt,
+        None => {
             return log_and_respond(
-                backend_configs.clickhouse.clone(),
-                backend_configs.firebase.clone(),
+                backend_configs.log_writer.clone(),
                 StatusCode::BAD_REQUEST,
                 &felafax_token,
+                None,
                 Some(&request),
                 None,
                 None,
-                0,
-                Some("Invalid LLM name. Supported LLMs are: mamba, openai, claude".to_string()),
+                started.elapsed().as_millis() as u32,
+                Some("No providers configured for this customer".to_string()),
             )
             .await
+            .into_response();
         }
     };
 
-    match llm_response {
-        Ok(response) => {
-            log_and_respond(
-                backend_configs.clickhouse.clone(),
-                backend_configs.firebase.clone(),
-                StatusCode::OK,
-                &felafax_token,
-                Some(&request),
-                Some(&response),
-                Some(&customer_config.selected_llm_name),
-                0,
-                None,
-            )
-            .await
-        }
+    for attempt in attempts {
+        let (status_code, error) = stream_attempt_status(&attempt.outcome);
+        log_stats(
+            backend_configs.log_writer.clone(),
+            status_code,
+            &felafax_token,
+            Some(&request_group_id),
+            Some(&request),
+            None,
+            Some(&attempt.provider),
+            started.elapsed().as_millis() as u32,
+            Some(attempt.latency_ms),
+            error,
+        )
+        .await;
+    }
+
+    let llm_name = last_attempt.provider;
+    let mut provider_stream = match last_attempt.outcome {
+        Ok(stream) => stream,
         Err(e) => {
-            log_and_respond(
-                backend_configs.clickhouse.clone(),
-                backend_configs.firebase.clone(),
-                StatusCode::INTERNAL_SERVER_ERROR,
+            let status_code = if matches!(e, error::Error::InvalidRequest(_)) {
+                StatusCode::BAD_REQUEST
+            } else {
+                StatusCode::INTERNAL_SERVER_ERROR
+            };
+            log_stats(
+                backend_configs.log_writer.clone(),
+                status_code,
                 &felafax_token,
+                Some(&request_group_id),
                 Some(&request),
                 None,
-                Some(&customer_config.selected_llm_name),
-                0,
+                Some(&llm_name),
+                started.elapsed().as_millis() as u32,
+                Some(last_attempt.latency_ms),
                 Some(e.to_string()),
             )
-            .await
+            .await;
+            return (status_code, Json(json!({ "error": e.to_string() }))).into_response();
         }
-    }
+    };
+
+    let events = stream! {
+        let mut content = String::new();
+        let mut model = String::new();
+        let mut usage = None;
+        let mut stream_error = None;
+
+        while let Some(item) = provider_stream.next().await {
+            match item {
+                Ok(chunk) => {
+                    model = chunk.model.clone();
+                    if chunk.usage.is_some() {
+                        usage = chunk.usage.clone();
+                    }
+                    for choice in &chunk.choices {
+                        if let Some(delta) = &choice.delta.content {
+                            content.push_str(delta);
+                        }
+                    }
+                    yield Ok(Event::default().data(serde_json::to_string(&chunk).unwrap()));
+                }
+                Err(e) => {
+                    yield Ok(Event::default().data(json!({ "error": e.to_string() }).to_string()));
+                    stream_error = Some(e.to_string());
+                    break;
+                }
+            }
+        }
+
+        yield Ok(Event::default().data("[DONE]"));
+
+        let assembled_response = OaiChatCompletionResponse {
+            id: Uuid::new_v4().to_string(),
+            model,
+            choices: vec![OaiChatChoice {
+                index: 0,
+                message: OaiChatMessage {
+                    role: "assistant".to_string(),
+                    content,
+                },
+                finish_reason: Some("stop".to_string()),
+            }],
+            usage,
+        };
+
+        let status_code = if stream_error.is_some() {
+            StatusCode::INTERNAL_SERVER_ERROR
+        } else {
+            StatusCode::OK
+        };
+
+        log_stats(
+            backend_configs.log_writer.clone(),
+            status_code,
+            &felafax_token,
+            Some(&request_group_id),
+            Some(&request),
+            Some(&assembled_response),
+            Some(&llm_name),
+            started.elapsed().as_millis() as u32,
+            None,
+            stream_error,
+        )
+        .await;
+    };
+
+    let events: std::pin::Pin<Box<dyn futures_util::Stream<Item = Result<Event, Infallible>> + Send>> =
+        Box::pin(events);
+
+    Sse::new(events).into_response()
+}
+
+/// Watches for SIGTERM/SIGINT and forces a `log_writer` flush before exiting,
+/// since Shuttle's serve loop gives us no graceful-shutdown hook of its own
+/// to await the writer's buffered rows against.
+fn spawn_shutdown_flush(log_writer: log_writer::LogWriter) {
+    tokio::spawn(async move {
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                tokio::select! {
+                    _ = sigterm.recv() => {}
+                    _ = tokio::signal::ctrl_c() => {}
+                }
+            }
+            Err(e) => {
+                eprintln!("failed to install SIGTERM handler: {e}; falling back to SIGINT only");
+                let _ = tokio::signal::ctrl_c().await;
+            }
+        }
+
+        log_writer.flush_now().await;
+        std::process::exit(0);
+    });
 }
 
 #[shuttle_runtime::main]
@@ -308,19 +630,43 @@ async fn main(#[shuttle_runtime::Secrets] secrets: SecretStore) -> shuttle_axum:
     let clickhouse_client = Arc::new(clickhouse::Clickhouse::new(
         &click_house_url,
         &clickhouse_username,
-        &clickhouse_password,
+        clickhouse_password,
         &clickhouse_database,
     ));
 
+    let token_secret = secrets
+        .get("LLM_API_SECRET")
+        .unwrap_or_else(|| panic!("Error: LLM_API_SECRET not found in secrets."));
+
+    let key_encryption_secret = secrets
+        .get("LLM_KEY_ENCRYPTION_SECRET")
+        .unwrap_or_else(|| panic!("Error: LLM_KEY_ENCRYPTION_SECRET not found in secrets."));
+    let key_cipher = encryption::KeyCipher::new(&key_encryption_secret)
+        .unwrap_or_else(|e| panic!("Failed to initialise key cipher: {:?}", e));
+
+    // Background task batches request logs instead of inserting one at a
+    // time. Shuttle's own serve loop gives us no shutdown hook to await the
+    // task's join handle against, so instead we catch SIGTERM/SIGINT
+    // ourselves and force a flush before exiting, to avoid losing whatever
+    // is buffered between periodic ticks.
+    let (log_writer, _log_writer_task) = log_writer::LogWriter::spawn(clickhouse_client.clone());
+    spawn_shutdown_flush(log_writer.clone());
+
     let backend_configs = BackendConfigs {
         secrets,
         firebase,
         clickhouse: clickhouse_client,
+        token_secret,
+        config_cache: RwLock::new(HashMap::new()),
+        rate_limiter: rate_limit::RateLimiter::default(),
+        key_cipher,
+        log_writer,
     };
     let backend_configs = Arc::new(backend_configs);
 
     let router = Router::new()
         .route("/", get(hello))
+        .route("/v1/tokens", post(mint_token))
         .route("/v1/chat/completions", post(chat_completion))
         .with_state(backend_configs);
 
@@ -0,0 +1,78 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use tokio::sync::RwLock;
+
+use crate::clickhouse::{Clickhouse, TokenUsage};
+use crate::types::CustomerConfig;
+
+/// How long a customer's aggregated usage is trusted before we re-query
+/// ClickHouse, so a busy customer doesn't cause an aggregation query on
+/// every single request.
+const USAGE_CACHE_TTL: Duration = Duration::from_secs(5);
+
+pub enum Decision {
+    Allowed,
+    Exceeded { retry_after_secs: u64 },
+}
+
+struct CachedUsage {
+    usage: TokenUsage,
+    fetched_at: Instant,
+}
+
+/// In-process cache of recent per-customer token usage, backing the
+/// `max_tokens_per_minute` / `max_tokens_per_month` limits on `CustomerConfig`.
+#[derive(Default)]
+pub struct RateLimiter {
+    usage: RwLock<HashMap<String, CachedUsage>>,
+}
+
+impl RateLimiter {
+    pub async fn check(&self, clickhouse: &Clickhouse, customer_config: &CustomerConfig) -> Decision {
+        let usage = self
+            .usage_for(clickhouse, &customer_config.customer_id)
+            .await;
+
+        if let Some(limit) = customer_config.max_tokens_per_minute {
+            if usage.last_minute >= limit {
+                return Decision::Exceeded {
+                    retry_after_secs: 60,
+                };
+            }
+        }
+
+        if let Some(limit) = customer_config.max_tokens_per_month {
+            if usage.this_month >= limit {
+                return Decision::Exceeded {
+                    retry_after_secs: 3600,
+                };
+            }
+        }
+
+        Decision::Allowed
+    }
+
+    async fn usage_for(&self, clickhouse: &Clickhouse, customer_id: &str) -> TokenUsage {
+        if let Some(cached) = self.usage.read().await.get(customer_id) {
+            if cached.fetched_at.elapsed() < USAGE_CACHE_TTL {
+                return cached.usage;
+            }
+        }
+
+        let usage = clickhouse
+            .token_usage(customer_id)
+            .await
+            .unwrap_or_default();
+
+        self.usage.write().await.insert(
+            customer_id.to_string(),
+            CachedUsage {
+                usage,
+                fetched_at: Instant::now(),
+            },
+        );
+
+        usage
+    }
+}
@@ -0,0 +1,28 @@
+use derive_builder::Builder;
+
+#[derive(Debug, Clone, Default, Builder)]
+#[builder(setter(into, strip_option), default)]
+pub struct RequestLog {
+    pub id: String,
+    pub timestamp: i64,
+    pub customer_id: String,
+    /// Ties together every attempt made for a single incoming request when
+    /// a routing chain falls back across providers.
+    pub request_group_id: Option<String>,
+    pub request: Option<String>,
+    pub response: Option<String>,
+    pub llm_name: Option<String>,
+    pub llm_model: Option<String>,
+    pub prompt_tokens: Option<u32>,
+    pub completion_tokens: Option<u32>,
+    pub total_tokens: Option<u32>,
+    /// Wall-clock time for the whole request, including every attempt in a
+    /// routing chain and all the gateway's own overhead.
+    pub total_latency: u32,
+    /// Upstream provider latency for just this attempt, distinct from
+    /// `total_latency` so per-provider p50/p95 can be computed separately
+    /// from end-to-end latency. `None` for rows that aren't a routing
+    /// attempt (auth/validation/rate-limit failures logged before dispatch).
+    pub upstream_latency: Option<u32>,
+    pub error: Option<String>,
+}
@@ -0,0 +1,213 @@
+use std::time::{Duration, Instant};
+
+use tokio::time::sleep;
+
+use crate::client::claude::Claude;
+use crate::client::mamba::Mamba;
+use crate::client::openai::OpenAI;
+use crate::client::traits::{ChatStream, LlmClient};
+use crate::encryption::KeyCipher;
+use crate::error::Error;
+use crate::types::{CustomerConfig, OaiChatCompletionRequest, OaiChatCompletionResponse};
+
+const INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+const MAX_BACKOFF: Duration = Duration::from_secs(2);
+
+/// One provider attempt within a routing chain, meant to be logged as its
+/// own row so failover behavior is visible in ClickHouse.
+pub struct Attempt {
+    pub provider: String,
+    pub latency_ms: u32,
+    pub outcome: Result<OaiChatCompletionResponse, Error>,
+}
+
+/// Providers to try, in order, for a customer. Falls back to just
+/// `selected_llm_name` when no explicit routing policy is configured.
+fn chain_for(customer_config: &CustomerConfig) -> Vec<String> {
+    if customer_config.routing_policy.is_empty() {
+        vec![customer_config.selected_llm_name.clone()]
+    } else {
+        customer_config.routing_policy.clone()
+    }
+}
+
+async fn call_provider(
+    provider: &str,
+    api_key: &str,
+    request: OaiChatCompletionRequest,
+) -> Result<OaiChatCompletionResponse, Error> {
+    match provider {
+        "claude" => Claude::new().with_api_key(api_key).chat(request).await,
+        "openai" => OpenAI::new().with_api_key(api_key).chat(request).await,
+        "jamba" => Mamba::new().with_api_key(api_key).chat(request).await,
+        other => Err(Error::InvalidRequest(format!(
+            "Invalid LLM name '{other}'. Supported LLMs are: mamba, openai, claude"
+        ))),
+    }
+}
+
+/// Dispatches a request across the customer's routing chain, retrying the
+/// next provider on a retryable failure with exponential backoff. Returns
+/// every attempt made, in order, so callers can log one row per attempt.
+pub async fn dispatch(
+    key_cipher: &KeyCipher,
+    customer_config: &CustomerConfig,
+    request: &OaiChatCompletionRequest,
+) -> Vec<Attempt> {
+    let mut attempts = Vec::new();
+    let mut backoff = INITIAL_BACKOFF;
+
+    let chain = chain_for(customer_config);
+    let last_index = chain.len().saturating_sub(1);
+
+    for (index, provider) in chain.into_iter().enumerate() {
+        let api_key = match key_cipher.decrypt_provider_key(customer_config, &provider) {
+            Ok(api_key) => api_key,
+            Err(e) => {
+                attempts.push(Attempt {
+                    provider,
+                    latency_ms: 0,
+                    outcome: Err(e),
+                });
+                continue;
+            }
+        };
+
+        let started = Instant::now();
+        let outcome = call_provider(&provider, &api_key, request.clone()).await;
+        let latency_ms = started.elapsed().as_millis() as u32;
+        let retryable = outcome.as_ref().err().is_some_and(Error::is_retryable);
+
+        attempts.push(Attempt {
+            provider,
+            latency_ms,
+            outcome,
+        });
+
+        if !should_backoff(retryable, index == last_index) {
+            break;
+        }
+
+        sleep(backoff).await;
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+
+    attempts
+}
+
+/// One provider attempt when establishing a streamed chat completion. Only
+/// covers connecting and validating the response, not the body of the
+/// stream itself — once a provider's stream starts, `chat_completion_stream`
+/// forwards it as-is rather than retrying mid-stream.
+pub struct StreamAttempt {
+    pub provider: String,
+    pub latency_ms: u32,
+    pub outcome: Result<ChatStream, Error>,
+}
+
+async fn call_provider_stream(
+    provider: &str,
+    api_key: &str,
+    request: OaiChatCompletionRequest,
+) -> Result<ChatStream, Error> {
+    match provider {
+        "claude" => {
+            Claude::new()
+                .with_api_key(api_key)
+                .chat_stream(request)
+                .await
+        }
+        "openai" => {
+            OpenAI::new()
+                .with_api_key(api_key)
+                .chat_stream(request)
+                .await
+        }
+        "jamba" => {
+            Mamba::new()
+                .with_api_key(api_key)
+                .chat_stream(request)
+                .await
+        }
+        other => Err(Error::InvalidRequest(format!(
+            "Invalid LLM name '{other}'. Supported LLMs are: mamba, openai, claude"
+        ))),
+    }
+}
+
+/// Dispatches a `stream: true` request across the customer's routing chain,
+/// same failover/backoff semantics as `dispatch`, so a single provider
+/// outage doesn't take down streaming traffic any more than non-streaming.
+pub async fn dispatch_stream(
+    key_cipher: &KeyCipher,
+    customer_config: &CustomerConfig,
+    request: &OaiChatCompletionRequest,
+) -> Vec<StreamAttempt> {
+    let mut attempts = Vec::new();
+    let mut backoff = INITIAL_BACKOFF;
+
+    let chain = chain_for(customer_config);
+    let last_index = chain.len().saturating_sub(1);
+
+    for (index, provider) in chain.into_iter().enumerate() {
+        let api_key = match key_cipher.decrypt_provider_key(customer_config, &provider) {
+            Ok(api_key) => api_key,
+            Err(e) => {
+                attempts.push(StreamAttempt {
+                    provider,
+                    latency_ms: 0,
+                    outcome: Err(e),
+                });
+                continue;
+            }
+        };
+
+        let started = Instant::now();
+        let outcome = call_provider_stream(&provider, &api_key, request.clone()).await;
+        let latency_ms = started.elapsed().as_millis() as u32;
+        let retryable = outcome.as_ref().err().is_some_and(Error::is_retryable);
+
+        attempts.push(StreamAttempt {
+            provider,
+            latency_ms,
+            outcome,
+        });
+
+        if !should_backoff(retryable, index == last_index) {
+            break;
+        }
+
+        sleep(backoff).await;
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+
+    attempts
+}
+
+/// Whether the chain should pay the backoff delay after this attempt: only
+/// when the failure is retryable AND another provider is left to try, so a
+/// fully-exhausted chain doesn't sleep before returning its terminal error.
+fn should_backoff(retryable: bool, is_last_attempt: bool) -> bool {
+    retryable && !is_last_attempt
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backs_off_when_retryable_and_more_providers_remain() {
+        assert!(should_backoff(true, false));
+    }
+
+    #[test]
+    fn skips_backoff_on_the_last_provider_even_if_retryable() {
+        assert!(!should_backoff(true, true));
+    }
+
+    #[test]
+    fn skips_backoff_when_not_retryable() {
+        assert!(!should_backoff(false, false));
+        assert!(!should_backoff(false, true));
+    }
+}
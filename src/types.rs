@@ -0,0 +1,99 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OaiChatCompletionRequest {
+    pub model: String,
+    pub messages: Vec<OaiChatMessage>,
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    #[serde(default)]
+    pub stream: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OaiChatMessage {
+    pub role: String,
+    pub content: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OaiChatCompletionResponse {
+    pub id: String,
+    pub model: String,
+    pub choices: Vec<OaiChatChoice>,
+    #[serde(default)]
+    pub usage: Option<OaiUsage>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OaiChatChoice {
+    pub index: u32,
+    pub message: OaiChatMessage,
+    #[serde(default)]
+    pub finish_reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OaiUsage {
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+    pub total_tokens: u32,
+}
+
+/// One SSE frame of a streamed chat completion, shaped like OpenAI's
+/// `chat.completion.chunk` object regardless of which upstream produced it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OaiChatCompletionChunk {
+    pub id: String,
+    pub object: String,
+    pub model: String,
+    pub choices: Vec<OaiChatChunkChoice>,
+    #[serde(default)]
+    pub usage: Option<OaiUsage>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OaiChatChunkChoice {
+    pub index: u32,
+    pub delta: OaiChatDelta,
+    #[serde(default)]
+    pub finish_reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OaiChatDelta {
+    #[serde(default)]
+    pub role: Option<String>,
+    #[serde(default)]
+    pub content: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LlmConfig {
+    /// AES-256-GCM ciphertext (see `encryption::KeyCipher`), not the raw key.
+    pub api_key: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomerConfig {
+    pub customer_id: String,
+    pub selected_llm_name: String,
+    pub llm_configs: HashMap<String, LlmConfig>,
+    #[serde(default)]
+    pub max_tokens_per_minute: Option<u64>,
+    #[serde(default)]
+    pub max_tokens_per_month: Option<u64>,
+    /// Ordered providers to try for this customer. Empty means "just
+    /// `selected_llm_name`, no fallback".
+    #[serde(default)]
+    pub routing_policy: Vec<String>,
+}
+
+/// Response body for `POST /v1/tokens`.
+#[derive(Debug, Clone, Serialize)]
+pub struct TokenResponse {
+    pub access_token: String,
+    pub token_type: &'static str,
+    pub expires_in: i64,
+}